@@ -4,10 +4,15 @@
 
 #![allow(dead_code)]
 
-use std::io::Write;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
 
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
 use serde::{Deserialize, Serialize};
-use signature::{Signer, Verifier};
+use signature::digest::Digest;
+use signature::{DigestSigner, DigestVerifier, Signer, Verifier};
 
 use crate::Error;
 
@@ -67,6 +72,251 @@ impl Envelope {
         signature.verify(&self.payload_type, &self.payload, verifier)?;
         Ok(&self.payload)
     }
+
+    /// Like [`Envelope::sign`], but feeds the PAE prefix and payload into a
+    /// [`Digest`] separately instead of first concatenating them into one
+    /// buffer, avoiding the extra allocation and copy [`pre_authentication_encoding`]
+    /// makes on every call. Produces the same signature as `sign` for `S`
+    /// when `D` is the digest `S`'s plain signer uses (SHA-256 for P-256,
+    /// SHA-384 for P-384); any other `D` yields a signature `verify` rejects.
+    ///
+    /// `S` is restricted to [`DigestEquivalentToSign`] (currently the ECDSA
+    /// P-256/P-384 signature types): that's what guarantees the digest this
+    /// signs matches what `sign` signs. Ed25519 can't support this, since
+    /// `ed25519_dalek`'s `DigestSigner` implements the distinct Ed25519ph
+    /// (prehash) scheme rather than plain Ed25519.
+    pub fn sign_streaming<D, S>(
+        &mut self,
+        key_id: String,
+        signer: impl DigestSigner<D, S>,
+    ) -> Result<(), Error>
+    where
+        D: Digest,
+        S: DigestEquivalentToSign,
+    {
+        if self.signatures().any(|s| s.key_id == key_id) {
+            return Err(Error::InvalidSigningKey(
+                format!("already has a signature with key_id {:?}", key_id).into(),
+            ));
+        }
+        self.signatures.push(Signature::sign_streaming(
+            &self.payload_type,
+            self.payload.len() as u64,
+            self.payload.as_slice(),
+            key_id,
+            signer,
+        )?);
+        Ok(())
+    }
+
+    /// Like [`Envelope::verify`], but via [`Signature::verify_streaming`]. See
+    /// [`Envelope::sign_streaming`] for the `S: DigestEquivalentToSign` restriction.
+    pub fn verify_streaming<D, S>(
+        &self,
+        key_id: &str,
+        verifier: impl DigestVerifier<D, S>,
+    ) -> Result<&[u8], Error>
+    where
+        D: Digest,
+        S: DigestEquivalentToSign,
+    {
+        let signature = self
+            .signatures()
+            .find(|s| s.key_id == key_id)
+            .ok_or_else(|| {
+                Error::InvalidSigningKey(format!("no signature with key_id {:?}", key_id).into())
+            })?;
+        signature.verify_streaming(
+            &self.payload_type,
+            self.payload.len() as u64,
+            self.payload.as_slice(),
+            verifier,
+        )?;
+        Ok(&self.payload)
+    }
+
+    /// Verify against a heterogeneous [`KeySet`], returning the payload and the
+    /// `key_id`s of every signature that verified. Unrecognized or failing
+    /// signatures are skipped; the call fails only if no signature verifies.
+    pub fn verify_with<'a>(&'a self, keys: &KeySet) -> Result<(&'a [u8], Vec<&'a str>), Error> {
+        let msg = pre_authentication_encoding(self.payload_type.as_bytes(), &self.payload);
+
+        let verified: Vec<&str> = self
+            .signatures()
+            .filter(|s| {
+                keys.get(&s.key_id)
+                    .is_some_and(|key| key.verify_pae(&msg, &s.signature).is_ok())
+            })
+            .map(|s| s.key_id.as_str())
+            .collect();
+
+        if verified.is_empty() {
+            return Err(Error::InvalidSigningKey(
+                "no recognized key_id verified this envelope".into(),
+            ));
+        }
+
+        Ok((&self.payload, verified))
+    }
+
+    /// Verify that at least `threshold` *distinct* keys in `keys` signed this
+    /// envelope, returning the payload if so.
+    ///
+    /// This is for multi-party attestation policies (e.g. "must be co-signed
+    /// by 2 of these 3 release keys") that a single `key_id` check can't
+    /// express. Unknown or unrecognized `key_id`s in the envelope are ignored
+    /// rather than causing failure, and two signatures verifying under the
+    /// same underlying key only count once, so a compromised or duplicated
+    /// `key_id` can't be used to inflate the count.
+    pub fn verify_threshold<'a>(
+        &'a self,
+        keys: &KeySet,
+        threshold: usize,
+    ) -> Result<&'a [u8], Error> {
+        if threshold == 0 {
+            return Err(Error::InvalidSigningKey(
+                "threshold must be at least 1".into(),
+            ));
+        }
+
+        let msg = pre_authentication_encoding(self.payload_type.as_bytes(), &self.payload);
+
+        let mut verified_keys: Vec<Vec<u8>> = Vec::new();
+        for sig in self.signatures() {
+            let Some(key) = keys.get(&sig.key_id) else {
+                continue;
+            };
+            if key.verify_pae(&msg, &sig.signature).is_err() {
+                continue;
+            }
+            let fingerprint = key.fingerprint();
+            if !verified_keys.contains(&fingerprint) {
+                verified_keys.push(fingerprint);
+            }
+        }
+
+        if verified_keys.len() < threshold {
+            return Err(Error::InvalidSigningKey(
+                format!(
+                    "only {} of {} required signatures verified",
+                    verified_keys.len(),
+                    threshold
+                )
+                .into(),
+            ));
+        }
+
+        Ok(&self.payload)
+    }
+
+    /// Serialize a single signature over this envelope as a JWS compact
+    /// serialization (`BASE64URL(header) "." BASE64URL(payload) "." BASE64URL(sig)`),
+    /// so it can be consumed by the widely deployed JWS/JWT tooling that
+    /// doesn't speak DSSE.
+    ///
+    /// The protected header is `{"alg": <algorithm>, "kid": <key_id>, "typ":
+    /// <payload_type>}`. Because JWS signs `header_b64 "." payload_b64` while
+    /// DSSE PAE signs `DSSEv1 LEN ...`, this is a genuine re-sign over the JWS
+    /// signing input rather than a reuse of any [`Signature`] already on this
+    /// envelope.
+    pub fn to_jws_compact<S: signature::Signature>(
+        &self,
+        key_id: &str,
+        algorithm: Algorithm,
+        signer: impl Signer<S>,
+    ) -> Result<String, Error> {
+        let header = serde_json::json!({
+            "alg": algorithm.jws_alg(),
+            "kid": key_id,
+            "typ": self.payload_type,
+        });
+        let header_b64 = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header)?);
+        let payload_b64 = URL_SAFE_NO_PAD.encode(&self.payload);
+        let signing_input = format!("{header_b64}.{payload_b64}");
+
+        let signature = signer.try_sign(signing_input.as_bytes())?;
+        let sig_b64 = URL_SAFE_NO_PAD.encode(signature.as_bytes());
+
+        Ok(format!("{signing_input}.{sig_b64}"))
+    }
+
+}
+
+/// A JWS compact serialization imported via [`ImportedJws::from_compact`].
+///
+/// This deliberately isn't an [`Envelope`]: its signature is computed over
+/// `header_b64 "." payload_b64`, not the DSSE PAE message, so it can't be
+/// checked with [`Envelope::verify`] or [`Envelope::verify_with`] — use
+/// [`ImportedJws::verify`] instead.
+#[derive(Debug)]
+pub struct ImportedJws {
+    pub algorithm: Algorithm,
+    pub key_id: String,
+    pub payload_type: String,
+    pub payload: Vec<u8>,
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+impl ImportedJws {
+    /// Parse a JWS compact serialization produced by [`Envelope::to_jws_compact`]
+    /// (or any compatible JWS).
+    pub fn from_compact(s: &str) -> Result<Self, Error> {
+        let mut parts = s.split('.');
+        let (Some(header_b64), Some(payload_b64), Some(sig_b64), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(Error::InvalidSigningKey(
+                "malformed JWS compact serialization".into(),
+            ));
+        };
+
+        let header_bytes = URL_SAFE_NO_PAD
+            .decode(header_b64)
+            .map_err(|e| Error::InvalidSigningKey(e.to_string().into()))?;
+        let header: serde_json::Value = serde_json::from_slice(&header_bytes)?;
+
+        let alg = header["alg"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidSigningKey("JWS header missing alg".into()))?;
+        let algorithm = Algorithm::from_jws_alg(alg)?;
+
+        let key_id = header["kid"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidSigningKey("JWS header missing kid".into()))?
+            .to_string();
+        let payload_type = header["typ"]
+            .as_str()
+            .ok_or_else(|| Error::InvalidSigningKey("JWS header missing typ".into()))?
+            .to_string();
+
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload_b64)
+            .map_err(|e| Error::InvalidSigningKey(e.to_string().into()))?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(sig_b64)
+            .map_err(|e| Error::InvalidSigningKey(e.to_string().into()))?;
+
+        Ok(Self {
+            algorithm,
+            key_id,
+            payload_type,
+            payload,
+            signing_input: format!("{header_b64}.{payload_b64}"),
+            signature,
+        })
+    }
+
+    /// Verify the JWS signature over `header_b64 "." payload_b64`, returning
+    /// the payload on success.
+    pub fn verify<S: signature::Signature>(
+        &self,
+        verifier: impl Verifier<S>,
+    ) -> Result<&[u8], Error> {
+        let signature = S::from_bytes(&self.signature)?;
+        verifier.verify(self.signing_input.as_bytes(), &signature)?;
+        Ok(&self.payload)
+    }
 }
 
 /// DSSE Signature
@@ -103,6 +353,74 @@ impl Signature {
         verifier.verify(&msg, &signature)?;
         Ok(())
     }
+
+    /// Like [`Signature::sign`], but incremental: the PAE prefix and `body`
+    /// are written into a [`Digest`] separately, via `body: impl Read`,
+    /// rather than first copied into one combined buffer the way
+    /// [`pre_authentication_encoding`] does. Useful for multi-megabyte
+    /// payloads already in memory (pass a `&[u8]`) or on disk (pass a
+    /// `File`). `body_len` must be the exact number of bytes `body` will
+    /// yield, since PAE's `LEN(body)` has to be written before the body
+    /// itself.
+    ///
+    /// `S` is restricted to [`DigestEquivalentToSign`] so the resulting
+    /// signature matches [`Signature::sign`]; see that trait for why Ed25519
+    /// can't be supported here.
+    pub fn sign_streaming<D, S>(
+        payload_type: &str,
+        body_len: u64,
+        body: impl Read,
+        key_id: String,
+        signer: impl DigestSigner<D, S>,
+    ) -> Result<Self, Error>
+    where
+        D: Digest,
+        S: DigestEquivalentToSign,
+    {
+        let digest = pae_digest::<D>(payload_type.as_bytes(), body_len, body)?;
+        let signature = signer.try_sign_digest(digest)?.as_bytes().into();
+        Ok(Self { key_id, signature })
+    }
+
+    /// The streaming counterpart to [`Signature::verify`]; see
+    /// [`Signature::sign_streaming`].
+    pub fn verify_streaming<D, S>(
+        &self,
+        payload_type: &str,
+        body_len: u64,
+        body: impl Read,
+        verifier: impl DigestVerifier<D, S>,
+    ) -> Result<(), Error>
+    where
+        D: Digest,
+        S: DigestEquivalentToSign,
+    {
+        let signature = S::from_bytes(&self.signature)?;
+        let digest = pae_digest::<D>(payload_type.as_bytes(), body_len, body)?;
+        verifier.verify_digest(digest, &signature)?;
+        Ok(())
+    }
+}
+
+/// Marks signature types for which [`DigestSigner`]/[`DigestVerifier`]
+/// produce the same signature as [`Signer`]/[`Verifier`] over the same
+/// message — i.e. algorithms that sign a single hash of the message with no
+/// other domain separation. This is what [`Signature::sign_streaming`] and
+/// [`Envelope::sign_streaming`] rely on to interoperate with `sign`/`verify`.
+///
+/// Deliberately excludes `ed25519_dalek::Signature`: its `DigestSigner` impl
+/// is Ed25519ph (the prehashed variant defined by RFC 8032), a different
+/// scheme from plain Ed25519 with its own domain-separation byte, so its
+/// streaming signature would never match `Envelope::sign`.
+pub trait DigestEquivalentToSign: signature::Signature + sealed::Sealed {}
+
+impl DigestEquivalentToSign for p256::ecdsa::Signature {}
+impl DigestEquivalentToSign for p384::ecdsa::Signature {}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for p256::ecdsa::Signature {}
+    impl Sealed for p384::ecdsa::Signature {}
 }
 
 // PAE(type, body) = "DSSEv1" + SP + LEN(type) + SP + type + SP + LEN(body) + SP + body
@@ -117,6 +435,161 @@ fn pre_authentication_encoding(type_: &[u8], body: &[u8]) -> Vec<u8> {
     buf
 }
 
+/// Hashes PAE(`type_`, body) into a fresh digest without ever holding the
+/// whole message in one buffer: the prefix is written directly, then `body`
+/// is streamed through in chunks via [`io::copy`].
+fn pae_digest<D: Digest>(type_: &[u8], body_len: u64, mut body: impl Read) -> Result<D, Error> {
+    struct DigestWriter<'a, D>(&'a mut D);
+
+    impl<D: Digest> Write for DigestWriter<'_, D> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.update(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut digest = D::new();
+    digest.update(b"DSSEv1 ");
+    digest.update(type_.len().to_string().as_bytes());
+    digest.update(b" ");
+    digest.update(type_);
+    digest.update(format!(" {body_len} ").as_bytes());
+    io::copy(&mut body, &mut DigestWriter(&mut digest))?;
+    Ok(digest)
+}
+
+/// The signature algorithm backing a [`VerificationKey`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Ed25519,
+    EcdsaP256,
+    EcdsaP384,
+    Rsa,
+}
+
+impl Algorithm {
+    /// The JWS `alg` header value for this algorithm (RFC 7518).
+    fn jws_alg(self) -> &'static str {
+        match self {
+            Self::Ed25519 => "EdDSA",
+            Self::EcdsaP256 => "ES256",
+            Self::EcdsaP384 => "ES384",
+            Self::Rsa => "RS256",
+        }
+    }
+
+    /// Recover the algorithm from a JWS `alg` header value.
+    fn from_jws_alg(alg: &str) -> Result<Self, Error> {
+        match alg {
+            "EdDSA" => Ok(Self::Ed25519),
+            "ES256" => Ok(Self::EcdsaP256),
+            "ES384" => Ok(Self::EcdsaP384),
+            "RS256" => Ok(Self::Rsa),
+            other => Err(Error::InvalidSigningKey(
+                format!("unsupported JWS alg {other:?}").into(),
+            )),
+        }
+    }
+}
+
+/// A public key used to verify a [`Signature`], tagged with its [`Algorithm`].
+#[derive(Clone, Debug)]
+pub enum VerificationKey {
+    Ed25519(ed25519_dalek::VerifyingKey),
+    EcdsaP256(p256::ecdsa::VerifyingKey),
+    EcdsaP384(p384::ecdsa::VerifyingKey),
+    Rsa(Box<rsa::pkcs1v15::VerifyingKey<sha2::Sha256>>),
+}
+
+impl VerificationKey {
+    /// The algorithm this key verifies signatures for.
+    pub fn algorithm(&self) -> Algorithm {
+        match self {
+            Self::Ed25519(_) => Algorithm::Ed25519,
+            Self::EcdsaP256(_) => Algorithm::EcdsaP256,
+            Self::EcdsaP384(_) => Algorithm::EcdsaP384,
+            Self::Rsa(_) => Algorithm::Rsa,
+        }
+    }
+
+    fn verify_pae(&self, msg: &[u8], sig: &[u8]) -> Result<(), Error> {
+        match self {
+            Self::Ed25519(key) => {
+                let sig = <ed25519_dalek::Signature as signature::Signature>::from_bytes(sig)?;
+                key.verify(msg, &sig)?;
+            }
+            Self::EcdsaP256(key) => {
+                let sig = <p256::ecdsa::Signature as signature::Signature>::from_bytes(sig)?;
+                key.verify(msg, &sig)?;
+            }
+            Self::EcdsaP384(key) => {
+                let sig = <p384::ecdsa::Signature as signature::Signature>::from_bytes(sig)?;
+                key.verify(msg, &sig)?;
+            }
+            Self::Rsa(key) => {
+                let sig = <rsa::pkcs1v15::Signature as signature::Signature>::from_bytes(sig)?;
+                key.verify(msg, &sig)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Canonical encoded bytes of the underlying public key, used to tell
+    /// whether two `key_id`s actually point at the same physical key.
+    fn fingerprint(&self) -> Vec<u8> {
+        match self {
+            Self::Ed25519(key) => key.as_bytes().to_vec(),
+            Self::EcdsaP256(key) => key.to_encoded_point(true).as_bytes().to_vec(),
+            Self::EcdsaP384(key) => key.to_encoded_point(true).as_bytes().to_vec(),
+            Self::Rsa(key) => rsa::pkcs1::EncodeRsaPublicKey::to_pkcs1_der(key.as_ref())
+                .expect("valid RSA public key")
+                .into_vec(),
+        }
+    }
+}
+
+/// A collection of [`VerificationKey`]s indexed by `key_id`.
+#[derive(Clone, Debug, Default)]
+pub struct KeySet(HashMap<String, VerificationKey>);
+
+impl KeySet {
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    /// Register `key` under `key_id`, returning the key previously registered
+    /// there, if any.
+    pub fn insert(
+        &mut self,
+        key_id: impl Into<String>,
+        key: VerificationKey,
+    ) -> Option<VerificationKey> {
+        self.0.insert(key_id.into(), key)
+    }
+
+    pub fn get(&self, key_id: &str) -> Option<&VerificationKey> {
+        self.0.get(key_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromIterator<(String, VerificationKey)> for KeySet {
+    fn from_iter<T: IntoIterator<Item = (String, VerificationKey)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,4 +654,128 @@ mod tests {
 
         assert_eq!(verified_payload, payload);
     }
+
+    #[test]
+    fn verify_with_mixed_algorithms() {
+        let payload = b"Payload";
+        let mut envelope = Envelope::new("VerifyWith".to_string(), payload.to_vec());
+
+        let ecdsa_key = spec_key();
+        envelope
+            .sign("ecdsa".to_string(), &ecdsa_key)
+            .expect("sign failed");
+
+        let ed25519_key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+        envelope
+            .sign("ed25519".to_string(), &ed25519_key)
+            .expect("sign failed");
+
+        let mut keys = KeySet::new();
+        keys.insert("ecdsa", VerificationKey::EcdsaP256(*ecdsa_key.verifying_key()));
+        keys.insert("ed25519", VerificationKey::Ed25519(ed25519_key.verifying_key()));
+        keys.insert(
+            "unused",
+            VerificationKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&[9u8; 32]).verifying_key()),
+        );
+
+        let (verified_payload, mut verified_ids) =
+            envelope.verify_with(&keys).expect("verify_with failed");
+        verified_ids.sort_unstable();
+
+        assert_eq!(verified_payload, payload);
+        assert_eq!(verified_ids, vec!["ecdsa", "ed25519"]);
+    }
+
+    #[test]
+    fn sign_streaming_matches_sign_for_ecdsa() {
+        let payload_type = "StreamingMatch";
+        let payload = b"Payload";
+
+        let sig = Signature::sign(payload_type, payload, "KeyId".to_string(), spec_key())
+            .expect("sign failed");
+        let streaming_sig = Signature::sign_streaming::<sha2::Sha256, _>(
+            payload_type,
+            payload.len() as u64,
+            &payload[..],
+            "KeyId".to_string(),
+            spec_key(),
+        )
+        .expect("sign_streaming failed");
+
+        assert_eq!(sig.signature, streaming_sig.signature);
+
+        streaming_sig
+            .verify(payload_type, payload, spec_key().verifying_key())
+            .expect("a sign_streaming signature must verify via plain verify");
+    }
+
+    #[test]
+    fn jws_export_verifies_as_real_jws() {
+        let payload = b"Payload";
+        let envelope = Envelope::new("Jws".to_string(), payload.to_vec());
+
+        let key = spec_key();
+        let jws = envelope
+            .to_jws_compact("KeyId", Algorithm::EcdsaP256, &key)
+            .expect("to_jws_compact failed");
+
+        let imported = ImportedJws::from_compact(&jws).expect("from_compact failed");
+        assert_eq!(imported.algorithm, Algorithm::EcdsaP256);
+        assert_eq!(imported.key_id, "KeyId");
+        assert_eq!(imported.payload_type, "Jws");
+
+        let verified_payload = imported
+            .verify(*key.verifying_key())
+            .expect("jws verify failed");
+        assert_eq!(verified_payload, payload);
+    }
+
+    #[test]
+    fn verify_threshold_rejects_zero() {
+        let envelope = Envelope::new("Threshold".to_string(), b"Payload".to_vec());
+        let keys = KeySet::new();
+        assert!(envelope.verify_threshold(&keys, 0).is_err());
+    }
+
+    #[test]
+    fn verify_threshold_counts_distinct_keys_only() {
+        let payload = b"Payload";
+        let mut envelope = Envelope::new("Threshold".to_string(), payload.to_vec());
+
+        let key_a = spec_key();
+        let key_b = ed25519_dalek::SigningKey::from_bytes(&[3u8; 32]);
+
+        envelope.sign("a".to_string(), &key_a).expect("sign failed");
+        envelope.sign("b".to_string(), &key_b).expect("sign failed");
+        // Same physical key as "a", registered under a second key_id.
+        envelope
+            .sign("a-dup".to_string(), &key_a)
+            .expect("sign failed");
+
+        let mut keys = KeySet::new();
+        keys.insert("a", VerificationKey::EcdsaP256(*key_a.verifying_key()));
+        keys.insert("a-dup", VerificationKey::EcdsaP256(*key_a.verifying_key()));
+        keys.insert("b", VerificationKey::Ed25519(key_b.verifying_key()));
+
+        assert_eq!(
+            envelope.verify_threshold(&keys, 2).expect("verify_threshold failed"),
+            payload
+        );
+        assert!(
+            envelope.verify_threshold(&keys, 3).is_err(),
+            "duplicate key_id for the same physical key must not count twice"
+        );
+    }
+
+    #[test]
+    fn verify_with_fails_when_no_key_recognized() {
+        let payload = b"Payload";
+        let mut envelope = Envelope::new("VerifyWith".to_string(), payload.to_vec());
+        envelope
+            .sign("ecdsa".to_string(), spec_key())
+            .expect("sign failed");
+
+        let keys = KeySet::new();
+        assert!(envelope.verify_with(&keys).is_err());
+    }
 }
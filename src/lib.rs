@@ -0,0 +1,4 @@
+mod dsse;
+pub mod keys;
+
+pub use dsse::{Algorithm, Envelope, ImportedJws, KeySet, Signature, VerificationKey};